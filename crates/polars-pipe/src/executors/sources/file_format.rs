@@ -0,0 +1,648 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use arrow::datatypes::ArrowSchema;
+use polars_core::POOL;
+use polars_core::error::*;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::Series;
+use polars_io::RowIndex;
+use polars_io::path_utils::is_cloud_url;
+use polars_io::pl_async::get_runtime;
+use polars_io::predicates::ScanIOPredicate;
+use polars_io::utils::slice::split_slice_at_file;
+use polars_plan::dsl::ScanSources;
+use polars_plan::plans::FileInfo;
+use polars_plan::prelude::FileScanOptions;
+use polars_plan::prelude::hive::HivePartitions;
+use polars_utils::IdxSize;
+use polars_utils::itertools::Itertools;
+use polars_utils::pl_str::PlSmallStr;
+
+use crate::executors::sources::get_source_index;
+use crate::operators::{DataChunk, PExecutionContext, Source, SourceResult};
+use crate::pipeline::determine_chunk_size;
+
+/// Given the running `processed_rows` total for a scan and the row count of the file about to
+/// be read, returns the slice (if any) that should be applied to this file and advances
+/// `processed_rows` by `n_rows_this_file`.
+///
+/// Shared by every [`FileFormat`] impl so slice splitting stays consistent across formats.
+pub(crate) fn compute_file_slice(
+    processed_rows: &AtomicUsize,
+    pre_slice: Option<(i64, usize)>,
+    n_rows_this_file: usize,
+) -> Option<(i64, usize)> {
+    let current_row_offset = processed_rows.fetch_add(n_rows_this_file, Ordering::Relaxed);
+    pre_slice.map(|slice| {
+        assert!(slice.0 >= 0);
+        let slice_start = slice.0 as usize;
+        let slice_end = slice_start + slice.1;
+        split_slice_at_file(
+            &mut current_row_offset.clone(),
+            n_rows_this_file,
+            slice_start,
+            slice_end,
+        )
+    })
+}
+
+/// Returns whether `pre_slice` has already been fully satisfied by `processed_rows`, i.e. no
+/// file still to come can contribute any in-range rows.
+///
+/// Drives [`GenericFileSource::next_unpruned_files`]'s early exhaustion: once this is `true` the
+/// remaining sources are never opened, probed for row counts, or hive-pruned.
+fn slice_is_satisfied(processed_rows: usize, pre_slice: Option<(i64, usize)>) -> bool {
+    pre_slice.is_some_and(|slice| processed_rows >= slice.0 as usize + slice.1)
+}
+
+/// Returns whether a pruned (unopened) file's row count must still be added to `processed_rows`.
+///
+/// Needed whenever something downstream counts rows across the whole scan - a `pre_slice` needs
+/// accurate offsets to find its start, and a `row_index` needs accurate offsets for its values -
+/// even though this particular file was never opened.
+fn needs_row_accounting(pre_slice: Option<(i64, usize)>, has_row_index: bool) -> bool {
+    pre_slice.is_some() || has_row_index
+}
+
+/// Returns whether every name in `live_columns` also appears in `available_columns`.
+///
+/// Shared helper behind [`GenericFileSource::hive_partitions_allow_file`]'s guard: a predicate can
+/// only be soundly evaluated against a reduced set of columns (e.g. hive partition literals) when
+/// none of its live columns fall outside that set.
+fn live_columns_are_subset<'a>(
+    live_columns: impl Iterator<Item = &'a PlSmallStr>,
+    available_columns: &[&PlSmallStr],
+) -> bool {
+    live_columns.into_iter().all(|name| available_columns.contains(&name))
+}
+
+/// A single scan source: either a path on some filesystem, or an in-memory buffer.
+///
+/// Buffer-backed sources never go through the async/cloud path and never carry
+/// `include_file_paths` (there is no path to include), but otherwise flow through the same
+/// prefetch/slice/hive machinery as paths.
+#[derive(Clone)]
+pub(crate) enum SourceRef {
+    Path(PathBuf),
+    Buffer(bytes::Bytes),
+}
+
+/// Arguments needed to open a batched reader for a single source file.
+///
+/// Bundled so [`FileFormat::open_batched_reader_sync`]/`_async` have a stable signature no
+/// matter how many knobs a given format needs.
+pub(crate) struct OpenArgs<'a> {
+    pub(crate) index: usize,
+    pub(crate) first_schema: &'a ArrowSchema,
+    pub(crate) projected_schema: Option<&'a ArrowSchema>,
+    pub(crate) allow_missing_columns: bool,
+    pub(crate) row_index: Option<RowIndex>,
+    pub(crate) predicate: Option<ScanIOPredicate>,
+    pub(crate) hive_partitions: Option<Vec<Series>>,
+    pub(crate) include_file_path: Option<(PlSmallStr, Arc<str>)>,
+    pub(crate) pre_slice: Option<(i64, usize)>,
+    pub(crate) processed_rows: &'a AtomicUsize,
+    pub(crate) chunk_size: usize,
+}
+
+/// A batch-producing reader for one already-opened source file.
+#[async_trait::async_trait]
+pub(crate) trait FormatReader: Send {
+    async fn next_batches(&mut self, n: usize) -> PolarsResult<Option<Vec<DataFrame>>>;
+}
+
+/// Format-specific half of the streaming scan machinery.
+///
+/// Implement this for a file format to drive it through [`GenericFileSource`], which owns the
+/// format-agnostic orchestration: path iteration, prefetching, slice splitting, hive partition
+/// materialization, row-index offsetting and `processed_rows` accounting. A new scan source is
+/// then ~one impl of this trait rather than a copy of the orchestration logic.
+#[async_trait::async_trait]
+pub(crate) trait FileFormat: Send + Sync + Sized {
+    type Reader: FormatReader;
+
+    /// Infer the Arrow schema of a single source.
+    ///
+    /// Used as a fallback in [`GenericFileSource::new`] when `file_info.reader_schema` wasn't
+    /// already resolved during planning.
+    fn infer_schema(&self, source: &SourceRef) -> PolarsResult<ArrowSchema>;
+
+    /// Cheaply determine the number of rows in a single source.
+    fn num_rows(&self, source: &SourceRef) -> PolarsResult<usize>;
+
+    /// Open a batched reader for `source`, applying projection, slicing, row-index offsetting,
+    /// hive partition columns and predicate pushdown.
+    ///
+    /// Called for every [`SourceRef::Buffer`] source, since those never go through the async
+    /// path.
+    fn open_batched_reader_sync(
+        &self,
+        source: &SourceRef,
+        args: OpenArgs<'_>,
+    ) -> PolarsResult<Self::Reader>;
+
+    /// Async counterpart of [`FileFormat::open_batched_reader_sync`], used for cloud paths (and
+    /// for any path when async is forced).
+    /// Never called with a [`SourceRef::Buffer`].
+    ///
+    /// Must not be run concurrently when a slice or row index is present, as callers rely on
+    /// `processed_rows` being read/incremented in file order.
+    async fn open_batched_reader_async(
+        &self,
+        source: &SourceRef,
+        args: OpenArgs<'_>,
+    ) -> PolarsResult<Self::Reader>;
+}
+
+/// A source that has passed hive partition pruning and is ready to be opened.
+struct PreparedFile {
+    index: usize,
+    source: SourceRef,
+    chunk_size: usize,
+    hive_partitions: Option<Vec<Series>>,
+}
+
+/// Generic streaming scan driver shared by all [`FileFormat`] sources.
+///
+/// Owns path iteration, prefetching, hive partition materialization, row-index offsetting and
+/// `processed_rows` accounting; all format-specific reader construction is delegated to `F`.
+pub(crate) struct GenericFileSource<F: FileFormat> {
+    format: F,
+    batched_readers: VecDeque<F::Reader>,
+    n_threads: usize,
+    processed_paths: usize,
+    processed_rows: AtomicUsize,
+    iter: Range<usize>,
+    sources: ScanSources,
+    file_options: Box<FileScanOptions>,
+    hive_parts: Option<Arc<Vec<HivePartitions>>>,
+    verbose: bool,
+    run_async: bool,
+    prefetch_size: usize,
+    first_schema: Arc<ArrowSchema>,
+    projected_arrow_schema: Option<Arc<ArrowSchema>>,
+    predicate: Option<ScanIOPredicate>,
+}
+
+impl<F: FileFormat> GenericFileSource<F> {
+    pub(crate) fn new(
+        format: F,
+        sources: ScanSources,
+        file_options: Box<FileScanOptions>,
+        file_info: FileInfo,
+        hive_parts: Option<Arc<Vec<HivePartitions>>>,
+        verbose: bool,
+        predicate: Option<ScanIOPredicate>,
+    ) -> PolarsResult<Self> {
+        let n_threads = POOL.current_num_threads();
+
+        let iter = 0..sources.len();
+
+        let prefetch_size = polars_core::config::get_file_prefetch_size();
+        if verbose {
+            eprintln!("POLARS PREFETCH_SIZE: {}", prefetch_size)
+        }
+        // `force_async()` only makes sense for paths: a buffer source has no URI to resolve
+        // against a cloud filesystem, so forcing it down the async path would just fail to open.
+        let run_async = match sources.as_paths() {
+            Some(paths) => {
+                paths.first().map(is_cloud_url).unwrap_or(false) || polars_core::config::force_async()
+            },
+            None => false,
+        };
+
+        // `file_info.reader_schema` is normally already resolved by the IR-building phase, but
+        // fall back to asking the format to infer it from the first source (e.g. a source added
+        // after planning, or a format that defers schema resolution) rather than panicking.
+        let first_schema = match file_info.reader_schema.clone() {
+            Some(schema) => schema.unwrap_left(),
+            None => {
+                let first_source = if let Some(paths) = sources.as_paths() {
+                    SourceRef::Path(paths.first().ok_or_else(
+                        || polars_err!(ComputeError: "cannot infer schema: no sources to scan"),
+                    )?.clone())
+                } else if let Some(buffers) = sources.as_buffers() {
+                    SourceRef::Buffer(buffers.first().ok_or_else(
+                        || polars_err!(ComputeError: "cannot infer schema: no sources to scan"),
+                    )?.clone())
+                } else {
+                    polars_bail!(nyi = "streaming scanning of this source type");
+                };
+                Arc::new(format.infer_schema(&first_source)?)
+            },
+        };
+
+        let projected_arrow_schema = {
+            if let Some(with_columns) = file_options.with_columns.as_deref() {
+                Some(Arc::new(first_schema.try_project(with_columns)?))
+            } else {
+                None
+            }
+        };
+
+        let mut source = GenericFileSource {
+            format,
+            batched_readers: VecDeque::new(),
+            n_threads,
+            processed_paths: 0,
+            processed_rows: AtomicUsize::new(0),
+            file_options,
+            iter,
+            sources,
+            hive_parts,
+            verbose,
+            run_async,
+            prefetch_size,
+            first_schema,
+            projected_arrow_schema,
+            predicate,
+        };
+        // Already start downloading when we deal with cloud urls.
+        if run_async {
+            source.init_next_reader()?;
+        }
+        Ok(source)
+    }
+
+    fn init_next_reader(&mut self) -> PolarsResult<()> {
+        if !self.run_async {
+            // Don't do this for async as that would mean we run serially.
+            self.init_reader_sync()
+        } else {
+            Ok(())
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn prepare_init_reader(&self, index: usize) -> PolarsResult<(SourceRef, usize, Option<Vec<Series>>)> {
+        let source = if let Some(paths) = self.sources.as_paths() {
+            SourceRef::Path(paths[index].clone())
+        } else if let Some(buffers) = self.sources.as_buffers() {
+            SourceRef::Buffer(buffers[index].clone())
+        } else {
+            polars_bail!(nyi = "streaming scanning of this source type");
+        };
+
+        let hive_partitions = self
+            .hive_parts
+            .as_ref()
+            .map(|x| x[index].materialize_partition_columns());
+
+        let chunk_size = determine_chunk_size(
+            self.projected_arrow_schema
+                .as_ref()
+                .map_or(self.first_schema.len(), |x| x.len()),
+            self.n_threads,
+        )?;
+
+        if self.verbose {
+            eprintln!("STREAMING CHUNK SIZE: {chunk_size} rows")
+        }
+
+        Ok((source, chunk_size, hive_partitions))
+    }
+
+    /// Evaluate `self.predicate` against the (constant, per-file) hive partition literals and
+    /// return `false` when the predicate can provably never match this partition.
+    ///
+    /// Mirrors DataFusion's pruned partition listing: partition columns are constant for the
+    /// whole file, so a predicate that only touches partition columns can be evaluated once
+    /// against a single-row frame instead of opening the file.
+    fn hive_partitions_allow_file(&self, hive_partitions: &[Series]) -> PolarsResult<bool> {
+        let Some(predicate) = self.predicate.as_ref() else {
+            return Ok(true);
+        };
+        if hive_partitions.is_empty() {
+            return Ok(true);
+        }
+
+        // The predicate may also touch non-partition columns, which aren't present in
+        // `hive_partitions`; evaluating it against a partition-only frame would either error or
+        // silently mask those columns. Only evaluate when every live column of the predicate is
+        // actually a partition column - otherwise we can't prove anything here, so don't risk it.
+        let partition_names = hive_partitions.iter().map(|s| s.name()).collect_vec();
+        if !live_columns_are_subset(predicate.live_columns.iter(), &partition_names) {
+            return Ok(true);
+        }
+
+        let stat_df = DataFrame::new(hive_partitions.to_vec())?;
+        let mask = predicate.predicate.evaluate_io(&stat_df)?;
+        let mask = mask.bool()?;
+        Ok(mask.get(0).unwrap_or(true))
+    }
+
+    /// Account for a file that was pruned without being opened: if a `pre_slice` or `row_index`
+    /// is active, `processed_rows` still needs the file's row count added so later files keep a
+    /// correct offset.
+    fn account_for_pruned_file(&self, source: &SourceRef) -> PolarsResult<()> {
+        if needs_row_accounting(self.file_options.pre_slice, self.file_options.row_index.is_some()) {
+            let n_rows = self.format.num_rows(source)?;
+            self.processed_rows.fetch_add(n_rows, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Pull up to `count` files from `self.iter` that survive hive partition pruning, skipping
+    /// (and cheaply accounting for) any file whose partition literals provably fail the predicate.
+    ///
+    /// Once `processed_rows` has satisfied `pre_slice`, marks `iter` exhausted and returns
+    /// immediately: no further files are opened, and no more `num_rows`/metadata calls are made
+    /// for trailing files past the end of the requested slice.
+    fn next_unpruned_files(&mut self, count: usize) -> PolarsResult<Vec<PreparedFile>> {
+        let mut out = Vec::with_capacity(count);
+        while out.len() < count {
+            if slice_is_satisfied(self.processed_rows.load(Ordering::Relaxed), self.file_options.pre_slice) {
+                self.iter = 0..0;
+                break;
+            }
+
+            let Some(index) = self.iter.next() else {
+                break;
+            };
+            let (source, chunk_size, hive_partitions) = self.prepare_init_reader(index)?;
+
+            if let Some(hive_partitions) = hive_partitions.as_ref() {
+                if !self.hive_partitions_allow_file(hive_partitions)? {
+                    if self.verbose {
+                        eprintln!("SKIPPED FILE due to hive partition pruning (index {index})");
+                    }
+                    self.account_for_pruned_file(&source)?;
+                    continue;
+                }
+            }
+
+            out.push(PreparedFile {
+                index,
+                source,
+                chunk_size,
+                hive_partitions,
+            });
+        }
+        Ok(out)
+    }
+
+    fn open_args(
+        &self,
+        index: usize,
+        chunk_size: usize,
+        hive_partitions: Option<Vec<Series>>,
+        source: &SourceRef,
+    ) -> OpenArgs<'_> {
+        let include_file_path = match source {
+            SourceRef::Path(path) => self
+                .file_options
+                .include_file_paths
+                .as_ref()
+                .map(|x| (x.clone(), Arc::from(path.to_str().unwrap()))),
+            // There is no filesystem path to include for an in-memory buffer.
+            SourceRef::Buffer(_) => None,
+        };
+
+        let row_index = self.file_options.row_index.clone().map(|mut ri| {
+            ri.offset += self.processed_rows.load(Ordering::Relaxed) as IdxSize;
+            ri
+        });
+
+        OpenArgs {
+            index,
+            first_schema: &self.first_schema,
+            projected_schema: self.projected_arrow_schema.as_deref(),
+            allow_missing_columns: self.file_options.allow_missing_columns,
+            row_index,
+            predicate: self.predicate.clone(),
+            hive_partitions,
+            include_file_path,
+            pre_slice: self.file_options.pre_slice,
+            processed_rows: &self.processed_rows,
+            chunk_size,
+        }
+    }
+
+    fn init_reader_sync(&mut self) -> PolarsResult<()> {
+        let Some(file) = self.next_unpruned_files(1)?.pop() else {
+            return Ok(());
+        };
+        let args = self.open_args(file.index, file.chunk_size, file.hive_partitions, &file.source);
+        let batched_reader = self.format.open_batched_reader_sync(&file.source, args)?;
+        self.finish_init_reader(batched_reader)?;
+        Ok(())
+    }
+
+    fn finish_init_reader(&mut self, batched_reader: F::Reader) -> PolarsResult<()> {
+        self.batched_readers.push_back(batched_reader);
+        self.processed_paths += 1;
+        Ok(())
+    }
+
+    /// This function must NOT be run concurrently if there is a slice (or any operation that
+    /// requires `self.processed_rows` to be incremented in the correct order), as it does not
+    /// coordinate to increment the row offset in a properly ordered manner.
+    async fn init_reader_async(&self, file: &PreparedFile) -> PolarsResult<F::Reader> {
+        let args = self.open_args(
+            file.index,
+            file.chunk_size,
+            file.hive_partitions.clone(),
+            &file.source,
+        );
+        self.format
+            .open_batched_reader_async(&file.source, args)
+            .await
+    }
+
+    fn prefetch_files(&mut self) -> PolarsResult<()> {
+        // We already start downloading the next file, we can only do that if we don't have a limit.
+        // In the case of a limit we first must update the row count with the batch results.
+        //
+        // It is important we do this for a reasonable batch size, that's why we start this when we
+        // have just 2 readers left.
+        if self.run_async {
+            #[cfg(not(feature = "async"))]
+            panic!("activate 'async' feature");
+
+            #[cfg(feature = "async")]
+            {
+                use futures::{StreamExt, TryStreamExt};
+
+                if self.batched_readers.len() <= 2 || self.batched_readers.is_empty() {
+                    let files =
+                        self.next_unpruned_files(self.prefetch_size - self.batched_readers.len())?;
+                    let init_iter = files.iter().map(|file| self.init_reader_async(file));
+
+                    let needs_exact_processed_rows_count = self.file_options.pre_slice.is_some()
+                        || self.file_options.row_index.is_some();
+
+                    let batched_readers = if needs_exact_processed_rows_count {
+                        // We run serially to ensure we have a correct processed_rows count.
+                        get_runtime().block_in_place_on(async {
+                            futures::stream::iter(init_iter)
+                                .then(|x| x)
+                                .try_collect()
+                                .await
+                        })?
+                    } else {
+                        get_runtime()
+                            .block_in_place_on(async { futures::future::try_join_all(init_iter).await })?
+                    };
+
+                    for r in batched_readers {
+                        self.finish_init_reader(r)?;
+                    }
+                }
+            }
+        } else {
+            for _ in 0..self.prefetch_size - self.batched_readers.len() {
+                self.init_reader_sync()?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: FileFormat> Source for GenericFileSource<F> {
+    fn get_batches(&mut self, _context: &PExecutionContext) -> PolarsResult<SourceResult> {
+        self.prefetch_files()?;
+
+        let Some(mut reader) = self.batched_readers.pop_front() else {
+            // If there was no new reader, we depleted all of them and are finished.
+            return Ok(SourceResult::Finished);
+        };
+
+        let batches = get_runtime().block_in_place_on(reader.next_batches(self.n_threads))?;
+
+        Ok(match batches {
+            None => {
+                // reset the reader
+                self.init_next_reader()?;
+                return self.get_batches(_context);
+            },
+            Some(batches) => {
+                let idx_offset = get_source_index(0);
+                let out = batches
+                    .into_iter()
+                    .enumerate_u32()
+                    .map(|(i, data)| DataChunk {
+                        chunk_index: (idx_offset + i) as IdxSize,
+                        data,
+                    })
+                    .collect::<Vec<_>>();
+                get_source_index(out.len() as u32);
+
+                let result = SourceResult::GotMoreData(out);
+                // We are not yet done with this reader.
+                // Ensure it is used in next iteration.
+                self.batched_readers.push_front(reader);
+
+                result
+            },
+        })
+    }
+
+    fn fmt(&self) -> &str {
+        "generic file source"
+    }
+}
+
+// Note: `GenericFileSource::next_unpruned_files` and `account_for_pruned_file` themselves aren't
+// exercised end-to-end here. Doing so needs a real `ScanSources`/`FileScanOptions`/`FileInfo`/
+// `HivePartitions` to construct a `GenericFileSource`, and none of those types exist in this
+// source tree (only `polars-pipe` and a sliver of `polars-plan` are present, no `polars-io`/
+// `polars-core` definitions to build against). What *is* tested below is the exact decision logic
+// those methods call - `slice_is_satisfied` for the early-exhaustion check, `needs_row_accounting`
+// for the pruned-file row accounting, and `live_columns_are_subset` for the hive-pruning predicate
+// guard (whose absence was exactly the chunk0-2 regression fixed in a later commit) - so a
+// regression in any of those conditions would still be caught here; a full integration test of
+// the methods themselves is a gap that needs a buildable checkout to close.
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::{
+        compute_file_slice, live_columns_are_subset, needs_row_accounting, slice_is_satisfied,
+    };
+    use polars_utils::pl_str::PlSmallStr;
+
+    #[test]
+    fn compute_file_slice_no_pre_slice_advances_processed_rows_only() {
+        let processed_rows = AtomicUsize::new(10);
+        let slice = compute_file_slice(&processed_rows, None, 5);
+        assert_eq!(slice, None);
+        assert_eq!(processed_rows.load(std::sync::atomic::Ordering::Relaxed), 15);
+    }
+
+    #[test]
+    fn compute_file_slice_skips_file_entirely_before_slice_start() {
+        // processed_rows already covers [0, 100); the requested slice starts at 200, so this
+        // 50-row file should be skipped entirely (an empty, not `None`, slice).
+        let processed_rows = AtomicUsize::new(100);
+        let slice = compute_file_slice(&processed_rows, Some((200, 10)), 50);
+        assert_eq!(slice, Some((50, 0)));
+    }
+
+    #[test]
+    fn compute_file_slice_clamps_to_file_boundaries() {
+        // Slice [5, 15) against a file starting at processed_rows=0 with 10 rows: only rows
+        // [5, 10) of this file are in range.
+        let processed_rows = AtomicUsize::new(0);
+        let slice = compute_file_slice(&processed_rows, Some((5, 10)), 10);
+        assert_eq!(slice, Some((5, 5)));
+    }
+
+    #[test]
+    fn live_columns_are_subset_true_when_all_present() {
+        let a = PlSmallStr::from_static("a");
+        let b = PlSmallStr::from_static("b");
+        let live = vec![a.clone(), b.clone()];
+        let available = [&a, &b];
+        assert!(live_columns_are_subset(live.iter(), &available));
+    }
+
+    #[test]
+    fn live_columns_are_subset_false_when_one_missing() {
+        let a = PlSmallStr::from_static("a");
+        let b = PlSmallStr::from_static("b");
+        let live = vec![a.clone(), b.clone()];
+        let available = [&a];
+        assert!(!live_columns_are_subset(live.iter(), &available));
+    }
+
+    #[test]
+    fn live_columns_are_subset_true_for_empty_live_columns() {
+        let available: [&PlSmallStr; 0] = [];
+        assert!(live_columns_are_subset(std::iter::empty(), &available));
+    }
+
+    #[test]
+    fn slice_is_satisfied_false_without_a_pre_slice() {
+        assert!(!slice_is_satisfied(1_000_000, None));
+    }
+
+    #[test]
+    fn slice_is_satisfied_false_before_slice_end() {
+        assert!(!slice_is_satisfied(9, Some((0, 10))));
+    }
+
+    #[test]
+    fn slice_is_satisfied_true_once_slice_end_reached() {
+        assert!(slice_is_satisfied(10, Some((0, 10))));
+        assert!(slice_is_satisfied(20, Some((5, 10))));
+    }
+
+    #[test]
+    fn needs_row_accounting_false_without_slice_or_row_index() {
+        assert!(!needs_row_accounting(None, false));
+    }
+
+    #[test]
+    fn needs_row_accounting_true_with_pre_slice_only() {
+        assert!(needs_row_accounting(Some((0, 10)), false));
+    }
+
+    #[test]
+    fn needs_row_accounting_true_with_row_index_only() {
+        assert!(needs_row_accounting(None, true));
+    }
+}
@@ -0,0 +1,14 @@
+mod file_format;
+mod parquet;
+mod parquet_bloom;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+pub(crate) use parquet::ParquetSource;
+
+static CHUNK_INDEX: AtomicU32 = AtomicU32::new(0);
+
+/// Reserve `n` consecutive chunk indices and return the first one.
+pub(crate) fn get_source_index(n: u32) -> u32 {
+    CHUNK_INDEX.fetch_add(n, Ordering::Relaxed)
+}
@@ -0,0 +1,224 @@
+//! Parquet split-block bloom filter bit-math and row-group pruning.
+//!
+//! **Status: not wired up for real queries yet.** [`prune_row_groups_by_bloom_filter`] and its bit
+//! math below are implemented, and the bit math and value hashing are tested in isolation (the
+//! pruning loop itself has no test here), but [`equality_probes`] - the only function
+//! that turns a predicate into probes for it - unconditionally returns an empty `Vec`, because
+//! `ScanIOPredicate` doesn't currently expose the literal values being compared (only predicate
+//! evaluation and the set of live columns). Until a companion change to `ScanIOPredicate` in
+//! `polars-io` adds that, `bloom_probes` at every call site is always empty and this module prunes
+//! nothing for any query. Landing literal extraction is a separate, not-yet-scheduled follow-up.
+
+use polars_core::error::*;
+use polars_core::prelude::AnyValue;
+use polars_io::parquet::metadata::FileMetadataRef;
+use polars_utils::pl_str::PlSmallStr;
+
+/// The eight 32-bit salts mandated by the Parquet split-block bloom filter spec, used to derive
+/// one bit position per 32-bit word of a block from the lower 32 bits of the probe hash.
+const SALT: [u32; 8] = [
+    0x47b6_137b, 0x4497_4d91, 0x8824_ad5b, 0xa2b7_289d, 0x7054_95c7, 0x2df1_424b, 0x9efc_4947,
+    0x5c6b_fb31,
+];
+
+const BLOCK_SIZE_BYTES: usize = 32;
+
+/// Test a single 32-byte block for the 8 bits derived from `hash`'s lower 32 bits.
+///
+/// Bloom filters never produce false negatives, so returning `false` here means the value is
+/// *definitely absent*; returning `true` only means it *may* be present.
+fn block_may_contain(block: &[u8; BLOCK_SIZE_BYTES], hash: u32) -> bool {
+    for (i, salt) in SALT.iter().enumerate() {
+        let word = u32::from_le_bytes(block[i * 4..i * 4 + 4].try_into().unwrap());
+        let mask = 1u32 << ((hash.wrapping_mul(*salt)) >> 27);
+        if word & mask == 0 {
+            return false;
+        }
+    }
+    true
+}
+
+/// Test whether the split-block bloom filter stored in `filter_bytes` may contain `hash`.
+///
+/// `filter_bytes` is the raw bitset as stored in the column chunk (a multiple of 32 bytes, each
+/// block holding 8 `u32` words). The block to test is selected from the upper 32 bits of `hash`;
+/// the 8 bit positions within it are derived from the lower 32 bits.
+pub(crate) fn bloom_filter_may_contain(filter_bytes: &[u8], hash: u64) -> bool {
+    let num_blocks = filter_bytes.len() / BLOCK_SIZE_BYTES;
+    if num_blocks == 0 {
+        // Malformed/empty filter: don't claim absence.
+        return true;
+    }
+
+    let block_index = (((hash >> 32) * num_blocks as u64) >> 32) as usize;
+    let start = block_index * BLOCK_SIZE_BYTES;
+    let block: [u8; BLOCK_SIZE_BYTES] = filter_bytes[start..start + BLOCK_SIZE_BYTES]
+        .try_into()
+        .unwrap();
+
+    block_may_contain(&block, hash as u32)
+}
+
+/// Hash a scalar the way Parquet's bloom filter spec mandates (xxHash64, seed 0).
+fn hash_any_value(value: &AnyValue<'_>) -> Option<u64> {
+    use xxhash_rust::xxh64::xxh64;
+
+    Some(match value {
+        AnyValue::String(s) => xxh64(s.as_bytes(), 0),
+        AnyValue::StringOwned(s) => xxh64(s.as_bytes(), 0),
+        AnyValue::Int32(v) => xxh64(&v.to_le_bytes(), 0),
+        AnyValue::Int64(v) => xxh64(&v.to_le_bytes(), 0),
+        AnyValue::UInt32(v) => xxh64(&v.to_le_bytes(), 0),
+        AnyValue::UInt64(v) => xxh64(&v.to_le_bytes(), 0),
+        _ => return None,
+    })
+}
+
+/// A single equality (or `is_in`) probe to test against a column's bloom filter: the live column
+/// name, and the literal value(s) being compared against it.
+pub(crate) struct BloomProbe {
+    pub(crate) column: PlSmallStr,
+    pub(crate) values: Vec<AnyValue<'static>>,
+}
+
+/// Given equality probes extracted from the predicate, return the indices of row groups that
+/// must be scanned, i.e. every row group *except* those where at least one probe's bloom filter
+/// proves all of that probe's values are definitely absent.
+///
+/// Falls back to "must scan" (keeps the row group) whenever bloom filter bytes can't be fetched
+/// or parsed for a candidate column/row-group, since bloom filters only support pruning on
+/// definite absence.
+pub(crate) fn prune_row_groups_by_bloom_filter(
+    file: &mut std::fs::File,
+    metadata: &FileMetadataRef,
+    candidate_row_groups: &[usize],
+    probes: &[BloomProbe],
+) -> PolarsResult<Vec<usize>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    if probes.is_empty() {
+        return Ok(candidate_row_groups.to_vec());
+    }
+
+    let mut kept = Vec::with_capacity(candidate_row_groups.len());
+
+    'row_group: for &rg_idx in candidate_row_groups {
+        let Some(row_group) = metadata.row_groups.get(rg_idx) else {
+            kept.push(rg_idx);
+            continue;
+        };
+
+        for probe in probes {
+            // `probe.column` only ever names a top-level column (see `equality_probes`), so
+            // require the full schema path to be exactly that one segment - matching on the
+            // leaf name alone would also match a same-named field nested under a struct/list.
+            let Some(column_chunk) = row_group.columns.iter().find(|c| {
+                let path = &c.descriptor().path_in_schema;
+                path.len() == 1 && path.last() == Some(&probe.column)
+            }) else {
+                continue;
+            };
+
+            let Some((offset, length)) = column_chunk.bloom_filter_byte_range() else {
+                // No bloom filter for this column: can't prune on it, fall through to scan.
+                continue;
+            };
+
+            let mut filter_bytes = vec![0u8; length as usize];
+            let read_ok = file
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| file.read_exact(&mut filter_bytes))
+                .is_ok();
+            if !read_ok {
+                // Couldn't fetch the filter: fall back to scanning rather than risk a false prune.
+                kept.push(rg_idx);
+                continue 'row_group;
+            }
+
+            let all_absent = probe.values.iter().all(|value| {
+                hash_any_value(value)
+                    .map(|hash| !bloom_filter_may_contain(&filter_bytes, hash))
+                    .unwrap_or(false)
+            });
+
+            if all_absent {
+                // Every probed value is definitely absent from this column in this row group.
+                continue 'row_group;
+            }
+        }
+
+        kept.push(rg_idx);
+    }
+
+    Ok(kept)
+}
+
+/// Extract equality/`is_in` probes from the predicate that can be tested against bloom filters.
+///
+/// Always returns no probes today - see the module-level doc comment for why and what's needed
+/// to change that.
+pub(crate) fn equality_probes(
+    _predicate: &polars_io::predicates::ScanIOPredicate,
+) -> Vec<BloomProbe> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Set the 8 bits `block_may_contain` would check for `hash`'s lower 32 bits, mirroring a
+    /// real bloom filter insert so tests can build a filter containing known values.
+    fn insert_into_block(block: &mut [u8; BLOCK_SIZE_BYTES], hash: u32) {
+        for (i, salt) in SALT.iter().enumerate() {
+            let mut word = u32::from_le_bytes(block[i * 4..i * 4 + 4].try_into().unwrap());
+            word |= 1u32 << ((hash.wrapping_mul(*salt)) >> 27);
+            block[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    fn filter_with(hashes: &[u64], num_blocks: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; num_blocks * BLOCK_SIZE_BYTES];
+        for &hash in hashes {
+            let block_index = (((hash >> 32) * num_blocks as u64) >> 32) as usize;
+            let start = block_index * BLOCK_SIZE_BYTES;
+            let mut block: [u8; BLOCK_SIZE_BYTES] =
+                bytes[start..start + BLOCK_SIZE_BYTES].try_into().unwrap();
+            insert_into_block(&mut block, hash as u32);
+            bytes[start..start + BLOCK_SIZE_BYTES].copy_from_slice(&block);
+        }
+        bytes
+    }
+
+    #[test]
+    fn bloom_filter_may_contain_true_for_inserted_value() {
+        let hash = xxhash_rust::xxh64::xxh64(b"hello", 0);
+        let filter = filter_with(&[hash], 4);
+        assert!(bloom_filter_may_contain(&filter, hash));
+    }
+
+    #[test]
+    fn bloom_filter_may_contain_false_for_empty_filter() {
+        let filter = vec![0u8; 4 * BLOCK_SIZE_BYTES];
+        let hash = xxhash_rust::xxh64::xxh64(b"anything", 0);
+        assert!(!bloom_filter_may_contain(&filter, hash));
+    }
+
+    #[test]
+    fn bloom_filter_may_contain_conservatively_true_for_malformed_filter() {
+        // Not a multiple of BLOCK_SIZE_BYTES / zero blocks: can't prove absence, so default to
+        // "may contain" rather than risk a false prune.
+        assert!(bloom_filter_may_contain(&[], 123));
+        assert!(bloom_filter_may_contain(&[0u8; 10], 123));
+    }
+
+    #[test]
+    fn hash_any_value_matches_xxh64_for_supported_types() {
+        assert_eq!(
+            hash_any_value(&AnyValue::Int64(42)),
+            Some(xxhash_rust::xxh64::xxh64(&42i64.to_le_bytes(), 0))
+        );
+        assert_eq!(hash_any_value(&AnyValue::Null), None);
+    }
+
+}